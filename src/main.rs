@@ -2,41 +2,156 @@
 // Author:       Abe van der Wielen <info@avdw.dev>
 // Github:       github.com/the-abe/fencat
 // Description:  A simple FEN viewer.
-// Usage:        fencat (--flip) [FILE]
+// Usage:        fencat (--flip|--auto|--fen|--epd|--theme NAME|--ascii|--no-color) [FILE]
 // Example:      echo rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR | fencat
 // Example:      fencat fen.txt
-// TODO:         Add support for FEN strings with move counters.
-// TODO:         Add support for FEN strings with castling availability.
-// TODO:         Add support for FEN strings with color to move.
 
-use regex::Regex;
-use std::{env, io};
+use std::{env, fmt, io};
 
-// Currently only cares about the board position and active color.
-// TODO: Flip the board based on the active color.
-// See: https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation#Definition
-const FEN_REGEX: &str = r"([rnbqkpRNBQKP1-8]+\/){7}([rnbqkpRNBQKP1-8]+)\s*([bw])?";
+// A background/foreground color scheme plus glyph table used to render the
+// board. Centralizes what used to be hardcoded ANSI escapes and piece
+// characters, so a preset (or no color at all) can be swapped in from the
+// CLI instead of edited into the source.
+struct Theme {
+    background_dark: &'static str,
+    background_light: &'static str,
+    background_en_passant: &'static str,
+    foreground_white: &'static str,
+    foreground_black: &'static str,
+    reset: &'static str,
+    ascii: bool,
+}
+
+impl Theme {
+    // Default colors chosen to make sure both white and black pieces are
+    // visible on the background.
+    fn classic() -> Theme {
+        Theme {
+            background_dark: "\x1b[48;5;246m",
+            background_light: "\x1b[48;5;249m",
+            background_en_passant: "\x1b[48;5;222m",
+            foreground_white: "\x1b[38;5;231m",
+            foreground_black: "\x1b[38;5;0m",
+            reset: "\x1b[0m",
+            ascii: false,
+        }
+    }
+
+    // Stark black-and-white squares for terminals/eyes that need more
+    // contrast than the classic grays.
+    fn high_contrast() -> Theme {
+        Theme {
+            background_dark: "\x1b[48;5;0m",
+            background_light: "\x1b[48;5;15m",
+            background_en_passant: "\x1b[48;5;226m",
+            foreground_white: "\x1b[38;5;15m",
+            foreground_black: "\x1b[38;5;0m",
+            reset: "\x1b[0m",
+            ascii: false,
+        }
+    }
+
+    // A blue board, closer to the look of some online chess clients.
+    fn blue() -> Theme {
+        Theme {
+            background_dark: "\x1b[48;5;24m",
+            background_light: "\x1b[48;5;153m",
+            background_en_passant: "\x1b[48;5;220m",
+            foreground_white: "\x1b[38;5;231m",
+            foreground_black: "\x1b[38;5;0m",
+            reset: "\x1b[0m",
+            ascii: false,
+        }
+    }
+
+    // Look up a preset by its `--theme` name, falling back to `classic`
+    // for anything unrecognized.
+    fn named(name: &str) -> Theme {
+        match name {
+            "high-contrast" => Theme::high_contrast(),
+            "blue" => Theme::blue(),
+            _ => Theme::classic(),
+        }
+    }
+
+    // Strip every escape code, for `--no-color` output that's safe to pipe
+    // to a file. Piece glyphs are left as they are (see `--ascii`).
+    fn strip_colors(mut self) -> Theme {
+        self.background_dark = "";
+        self.background_light = "";
+        self.background_en_passant = "";
+        self.foreground_white = "";
+        self.foreground_black = "";
+        self.reset = "";
+        self
+    }
+}
+
+// The flags and optional FILE argument parsed out of argv.
+struct CliOptions {
+    file: Option<String>,
+    explicit_flip: bool,
+    auto: bool,
+    fen: bool,
+    epd: bool,
+    theme: Theme,
+}
+
+// Walk argv looking for recognized flags, treating any argument that isn't
+// one of them as the input FILE (the last such argument wins). `--theme`
+// additionally consumes the argument after it as the preset name.
+fn parse_args(args: &[String]) -> CliOptions {
+    let mut file = None;
+    let mut explicit_flip = false;
+    let mut auto = false;
+    let mut fen = false;
+    let mut epd = false;
+    let mut theme_name = "classic".to_string();
+    let mut no_color = false;
+    let mut ascii = false;
 
-// ANSI escape codes for colors.
-// TODO: Make these configurable. Readability is important. Maybe use preset color schemes?
-// Default colors chosen to make sure both white and black pieces are visible on the background.
-const BACKGROUND_DARK: &str = "\x1b[48;5;246m";
-const BACKGROUND_LIGHT: &str = "\x1b[48;5;249m";
-const RESET_COLOR: &str = "\x1b[0m";
+    let mut rest = args.iter().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--flip" | "-f" => explicit_flip = true,
+            "--auto" | "--pov" => auto = true,
+            "--fen" => fen = true,
+            "--epd" => epd = true,
+            "--ascii" => ascii = true,
+            "--no-color" => no_color = true,
+            "--theme" => {
+                if let Some(name) = rest.next() {
+                    theme_name = name.clone();
+                }
+            }
+            _ => file = Some(arg.clone()),
+        }
+    }
+
+    let mut theme = Theme::named(&theme_name);
+    if no_color {
+        theme = theme.strip_colors();
+    }
+    theme.ascii = ascii;
+
+    CliOptions {
+        file,
+        explicit_flip,
+        auto,
+        fen,
+        epd,
+        theme,
+    }
+}
 
 fn main() -> io::Result<()> {
     let args: Vec<String> = env::args().collect();
+    let options = parse_args(&args);
+
     // Check if the user has provided a FEN string in a file or stdin.
-    let mut fen = match args.len() {
-        2 => match std::fs::read_to_string(args[1].as_str()) {
-            Ok(fen) => fen,
-            Err(_) => String::new(),
-        },
-        3 => match std::fs::read_to_string(args[2].as_str()) {
-            Ok(fen) => fen,
-            Err(_) => String::new(),
-        },
-        _ => String::new(),
+    let mut fen = match &options.file {
+        Some(path) => std::fs::read_to_string(path).unwrap_or_default(),
+        None => String::new(),
     };
     // If the user has not provided a FEN string in a file, check if the user has provided a FEN in
     // stdin.
@@ -44,33 +159,54 @@ fn main() -> io::Result<()> {
         io::stdin().read_line(&mut fen)?;
     }
 
-    // Check if the FEN string is present and valid and print an error if it is not.
-    if fen.is_empty() || !Regex::is_match(&Regex::new(&FEN_REGEX).unwrap(), fen.as_str()) {
-        println!("No FEN string provided or not readable.");
+    // Check if a FEN string is present at all before trying to parse it.
+    if fen.is_empty() {
+        println!("No FEN string provided.");
         usage();
         std::process::exit(1);
     }
 
-    // Set flip if --flip is passed as an argument.
-    let flip = if args.len() > 1 {
-        args[1] == "--flip" || args[1] == "-f"
-    } else {
-        false
-    };
+    // Split the FEN string into its six fields, filling in defaults for
+    // anything missing after the board.
+    let fields = parse_fields(&fen);
+
+    // Validate the fields and print exactly what is wrong if they don't form
+    // a legal FEN.
+    if let Err(error) = validate_fields(&fields) {
+        println!("{}", error);
+        usage();
+        std::process::exit(1);
+    }
 
-    let active_color = match Regex::captures(&Regex::new(&FEN_REGEX).unwrap(), fen.as_str())
-        .unwrap() // Safe to unwrap because we know the regex matches.
-        .get(3) {
-        Some(color) => match color.as_str() {
-            "w" => "White",
-            "b" => "Black",
-            _ => "Unknown", // Should never happen because the regex only matches w and b.
-        },
-        None => "Unknown",
+    // --fen/--epd re-serialize the parsed position to a canonical string
+    // instead of drawing the board, so messy or partial input can be
+    // cleaned up for use in a pipeline.
+    if options.fen {
+        println!("{}", to_fen(&fields));
+        return Ok(());
+    }
+    if options.epd {
+        println!("{}", to_epd(&fields));
+        return Ok(());
+    }
+
+    let active_color = match fields.active_color.as_str() {
+        "w" => "White",
+        "b" => "Black",
+        _ => "Unknown",
     };
 
-    // Split the FEN string into lines.
-    let board_lines = split_fen(fen);
+    // Explicit --flip/-f always wins. Otherwise --auto/--pov orients the
+    // board from the active color, flipping to Black's perspective when
+    // it's Black to move.
+    let flip = options.explicit_flip || (options.auto && active_color == "Black");
+
+    // Split the board field into lines.
+    let board_lines = split_fen(&fields.board);
+
+    // The en passant target square, as (board line index, file index), so
+    // `chessify` can highlight that one empty square.
+    let en_passant_target = parse_square(&fields.en_passant).map(|(file, rank)| (8 - rank, file));
 
     // Print the board in the correct orientation.
     // Orientation is determined by the flip argument and changes:
@@ -80,54 +216,508 @@ fn main() -> io::Result<()> {
     if flip {
         println!("   h  g  f  e  d  c  b  a");
         for (i, line) in board_lines.iter().rev().enumerate() {
-            println!("{} {} {}", i + 1, chessify(line, i % 2 == 0, flip), i + 1);
+            let board_index = board_lines.len() - 1 - i;
+            let target_file = en_passant_target
+                .filter(|&(rank, _)| rank == board_index)
+                .map(|(_, file)| file);
+            println!(
+                "{} {} {}",
+                i + 1,
+                chessify(line, i % 2 == 0, flip, target_file, &options.theme),
+                i + 1
+            );
         }
         println!("   h  g  f  e  d  c  b  a");
     } else {
         println!("   a  b  c  d  e  f  g  h");
         for (i, line) in board_lines.iter().enumerate() {
-            println!("{} {} {}", 8 - i, chessify(line, i % 2 == 0, flip), 8 - i);
+            let target_file = en_passant_target
+                .filter(|&(rank, _)| rank == i)
+                .map(|(_, file)| file);
+            println!(
+                "{} {} {}",
+                8 - i,
+                chessify(line, i % 2 == 0, flip, target_file, &options.theme),
+                8 - i
+            );
         }
         println!("   a  b  c  d  e  f  g  h");
     }
 
+    // Resolve castling rights against the rooks actually on the board, so
+    // Shredder-FEN (rook files) and X-FEN (KQkq relative to the outermost
+    // rook) both come out the same normalized shape.
+    let castling_rights = resolve_castling(&fields.castling, &board_lines);
+
     println!("Active color: {}", active_color);
+    println!(
+        "{} / En passant: {} / Halfmove clock: {} / Move: {}",
+        describe_castling(&castling_rights),
+        fields.en_passant,
+        fields.halfmove_clock,
+        fields.fullmove_number
+    );
 
     Ok(())
 }
 
 // Print the usage information.
 // TODO: Be sure to update this if the usage changes.
-fn usage() -> () {
+fn usage() {
     println!("Fencat will read a FEN string from a file or stdin and print the chessboard.");
     println!("The FEN first FEN string found will be used.");
-    println!("Usage: fencat (--flip) [FILE]");
+    println!("Usage: fencat (--flip|--auto|--fen|--epd|--theme NAME|--ascii|--no-color) [FILE]");
     println!("Example: echo rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR | fencat");
     println!("Example: fencat fen.txt");
     println!("Example: fencat < fen.txt");
     println!("Example: fencat --flip fen.txt");
+    println!("Example: fencat --auto fen.txt  (flip to Black's perspective when Black is to move)");
+    println!("Example: fencat --fen fen.txt   (print the canonical FEN instead of the board)");
+    println!("Example: fencat --epd fen.txt   (print the position as EPD, without move counters)");
+    println!("Example: fencat --theme blue fen.txt  (presets: classic, high-contrast, blue)");
+    println!("Example: fencat --ascii fen.txt  (render pieces as RNBQKP/rnbqkp letters)");
+    println!("Example: fencat --no-color fen.txt  (strip all escape codes for piping to a file)");
 }
 
-// Split the FEN string into lines.
-// Takes the whole string and extracts the board through a regex.
-// Garbage before and after the board is ignored.
-fn split_fen(fen: String) -> Vec<String> {
-    // Split the FEN string into whitespace,
-    // Take the first part (the board),
-    // Split the board into lines by the "/",
-    // Collect the lines into a vector.
-    fen.split_whitespace()
-        .take(1).collect::<Vec<&str>>().join("") // Take the first part (the board).
+// Split the board field into ranks.
+fn split_fen(board: &str) -> Vec<String> {
+    board
         .split("/").take(8) // Take the first 8 lines. Should only be 8.
         .map(|s| s.to_string()).collect() // Turn the lines into Strings and collect.
 }
 
+// The six space-separated fields of a FEN string, parsed out individually.
+// See: https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation#Definition
+struct FenFields {
+    board: String,
+    active_color: String,
+    castling: String,
+    en_passant: String,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
+
+// Split a FEN string on ASCII whitespace into its six fields.
+// Trailing fields that are missing fall back to their FEN defaults:
+// `-` for castling and en passant, `0` for the halfmove clock and `1` for
+// the fullmove number. The counters fall back to their defaults as well
+// if they fail to parse as integers.
+fn parse_fields(fen: &str) -> FenFields {
+    let mut fields = fen.split_ascii_whitespace();
+
+    let board = fields.next().unwrap_or_default().to_string();
+    let active_color = fields.next().unwrap_or("w").to_string();
+    let castling = fields.next().unwrap_or("-").to_string();
+    let en_passant = fields.next().unwrap_or("-").to_string();
+    let halfmove_clock = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let fullmove_number = fields.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+
+    FenFields {
+        board,
+        active_color,
+        castling,
+        en_passant,
+        halfmove_clock,
+        fullmove_number,
+    }
+}
+
+// Expand a single rank, then re-compress it, merging consecutive empty
+// squares back into a single digit.
+fn compress_rank(rank: &str) -> String {
+    let mut compressed = String::new();
+    let mut empties = 0;
+
+    for square in expand_rank(rank) {
+        if square == '.' {
+            empties += 1;
+        } else {
+            if empties > 0 {
+                compressed.push_str(&empties.to_string());
+                empties = 0;
+            }
+            compressed.push(square);
+        }
+    }
+    if empties > 0 {
+        compressed.push_str(&empties.to_string());
+    }
+
+    compressed
+}
+
+// Re-serialize the board field into its canonical, minimally-compressed
+// form.
+fn serialize_board(board: &str) -> String {
+    split_fen(board)
+        .iter()
+        .map(|rank| compress_rank(rank))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Re-serialize a full, normalized FEN string, filling in defaults for any
+// field that was missing from the input.
+fn to_fen(fields: &FenFields) -> String {
+    format!(
+        "{} {} {} {} {} {}",
+        serialize_board(&fields.board),
+        fields.active_color,
+        fields.castling,
+        fields.en_passant,
+        fields.halfmove_clock,
+        fields.fullmove_number
+    )
+}
+
+// Re-serialize an EPD string: a FEN without the halfmove/fullmove
+// counters.
+fn to_epd(fields: &FenFields) -> String {
+    format!(
+        "{} {} {} {}",
+        serialize_board(&fields.board),
+        fields.active_color,
+        fields.castling,
+        fields.en_passant
+    )
+}
+
+// Everything that can be wrong with a FEN string's fields, with enough
+// detail to tell the user exactly what to fix.
+enum FenError {
+    InvalidRankCount { found: usize },
+    BadRankSum { rank: usize, sum: u32 },
+    InvalidSquareChar { rank: usize, ch: char },
+    MissingKing { color: &'static str },
+    MultipleKings { color: &'static str },
+    BadSideToMove { found: String },
+    BadCastling { found: String },
+    BadEnPassant { found: String },
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::InvalidRankCount { found } => {
+                write!(f, "Expected 8 ranks separated by '/', found {}.", found)
+            }
+            FenError::BadRankSum { rank, sum } => write!(
+                f,
+                "Rank {} has {} squares, expected 8.",
+                8 - rank,
+                sum
+            ),
+            FenError::InvalidSquareChar { rank, ch } => write!(
+                f,
+                "Rank {} contains the invalid square character '{}'.",
+                8 - rank,
+                ch
+            ),
+            FenError::MissingKing { color } => write!(f, "Missing the {} king.", color),
+            FenError::MultipleKings { color } => write!(f, "Found more than one {} king.", color),
+            FenError::BadSideToMove { found } => write!(
+                f,
+                "Invalid side to move '{}', expected 'w' or 'b'.",
+                found
+            ),
+            FenError::BadCastling { found } => {
+                write!(f, "Invalid castling availability '{}'.", found)
+            }
+            FenError::BadEnPassant { found } => {
+                write!(f, "Invalid en passant target square '{}'.", found)
+            }
+        }
+    }
+}
+
+// Validate the parsed fields of a FEN string, returning a descriptive
+// `FenError` for the first problem found.
+fn validate_fields(fields: &FenFields) -> Result<(), FenError> {
+    let ranks: Vec<&str> = fields.board.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::InvalidRankCount { found: ranks.len() });
+    }
+
+    let mut white_kings = 0;
+    let mut black_kings = 0;
+
+    for (rank, squares) in ranks.iter().enumerate() {
+        let mut sum = 0;
+        for ch in squares.chars() {
+            if let Some(digit) = ch.to_digit(10) {
+                if !(1..=8).contains(&digit) {
+                    return Err(FenError::InvalidSquareChar { rank, ch });
+                }
+                sum += digit;
+            } else if "rnbqkpRNBQKP".contains(ch) {
+                sum += 1;
+                match ch {
+                    'K' => white_kings += 1,
+                    'k' => black_kings += 1,
+                    _ => {}
+                }
+            } else {
+                return Err(FenError::InvalidSquareChar { rank, ch });
+            }
+        }
+        if sum != 8 {
+            return Err(FenError::BadRankSum { rank, sum });
+        }
+    }
+
+    if white_kings == 0 {
+        return Err(FenError::MissingKing { color: "white" });
+    }
+    if white_kings > 1 {
+        return Err(FenError::MultipleKings { color: "white" });
+    }
+    if black_kings == 0 {
+        return Err(FenError::MissingKing { color: "black" });
+    }
+    if black_kings > 1 {
+        return Err(FenError::MultipleKings { color: "black" });
+    }
+
+    if fields.active_color != "w" && fields.active_color != "b" {
+        return Err(FenError::BadSideToMove {
+            found: fields.active_color.clone(),
+        });
+    }
+
+    let castling_valid = fields.castling == "-"
+        || (!fields.castling.is_empty()
+            && fields
+                .castling
+                .chars()
+                .all(|c| "KQkqABCDEFGHabcdefgh".contains(c)));
+    if !castling_valid {
+        return Err(FenError::BadCastling {
+            found: fields.castling.clone(),
+        });
+    }
+
+    let en_passant_valid = fields.en_passant == "-"
+        || matches!(parse_square(&fields.en_passant), Some((_, 3 | 6)));
+    if !en_passant_valid {
+        return Err(FenError::BadEnPassant {
+            found: fields.en_passant.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+// A single castling right, normalized down to the file the granting rook
+// sits on and whether that puts it on the king-side or queen-side of the
+// king. This is the common shape that both standard `KQkq`, X-FEN (`KQkq`
+// resolved against the outermost rook) and Shredder-FEN (explicit rook
+// files) collapse into.
+struct CastlingRight {
+    file: char,
+    king_side: bool,
+}
+
+// Castling rights for both colors, in the order they appear in the castling
+// field (queen-side before king-side is not guaranteed).
+struct CastlingRights {
+    white: Vec<CastlingRight>,
+    black: Vec<CastlingRight>,
+}
+
+// Expand a rank's FEN characters into 8 squares, turning digit runs into
+// that many empty-square placeholders so pieces can be found by file.
+fn expand_rank(rank: &str) -> Vec<char> {
+    let mut squares = Vec::new();
+    for ch in rank.chars() {
+        match ch.to_digit(10) {
+            Some(n) => squares.extend(std::iter::repeat_n('.', n as usize)),
+            None => squares.push(ch),
+        }
+    }
+    squares
+}
+
+// The file ('a'..='h') of the first occurrence of `piece` on a rank.
+fn file_of(rank: &str, piece: char) -> Option<char> {
+    expand_rank(rank)
+        .iter()
+        .position(|&c| c == piece)
+        .map(|i| (b'a' + i as u8) as char)
+}
+
+// The files ('a'..='h') of every occurrence of `piece` on a rank.
+fn files_of(rank: &str, piece: char) -> Vec<char> {
+    expand_rank(rank)
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c == piece)
+        .map(|(i, _)| (b'a' + i as u8) as char)
+        .collect()
+}
+
+// Resolve the raw castling field against the rooks and kings actually
+// present on the board. `K`/`Q`/`k`/`q` resolve to the outermost rook on
+// the king-side/queen-side (correct for both standard chess and X-FEN).
+// `A`-`H`/`a`-`h` (Shredder-FEN) name the rook's file directly.
+fn resolve_castling(castling: &str, board_lines: &[String]) -> CastlingRights {
+    let white_back_rank = board_lines.get(7).map(String::as_str).unwrap_or("");
+    let black_back_rank = board_lines.first().map(String::as_str).unwrap_or("");
+
+    let white_king_file = file_of(white_back_rank, 'K');
+    let black_king_file = file_of(black_back_rank, 'k');
+    let white_rook_files = files_of(white_back_rank, 'R');
+    let black_rook_files = files_of(black_back_rank, 'r');
+
+    let mut rights = CastlingRights {
+        white: Vec::new(),
+        black: Vec::new(),
+    };
+
+    if castling == "-" {
+        return rights;
+    }
+
+    for token in castling.chars() {
+        match token {
+            'K' => {
+                if let Some(king_file) = white_king_file {
+                    if let Some(&file) = white_rook_files.iter().filter(|&&f| f > king_file).max() {
+                        rights.white.push(CastlingRight { file, king_side: true });
+                    }
+                }
+            }
+            'Q' => {
+                if let Some(king_file) = white_king_file {
+                    if let Some(&file) = white_rook_files.iter().filter(|&&f| f < king_file).min() {
+                        rights.white.push(CastlingRight { file, king_side: false });
+                    }
+                }
+            }
+            'k' => {
+                if let Some(king_file) = black_king_file {
+                    if let Some(&file) = black_rook_files.iter().filter(|&&f| f > king_file).max() {
+                        rights.black.push(CastlingRight { file, king_side: true });
+                    }
+                }
+            }
+            'q' => {
+                if let Some(king_file) = black_king_file {
+                    if let Some(&file) = black_rook_files.iter().filter(|&&f| f < king_file).min() {
+                        rights.black.push(CastlingRight { file, king_side: false });
+                    }
+                }
+            }
+            'A'..='H' => {
+                let file = (token as u8 - b'A' + b'a') as char;
+                if let Some(king_file) = white_king_file {
+                    rights.white.push(CastlingRight { file, king_side: file > king_file });
+                }
+            }
+            'a'..='h' => {
+                if let Some(king_file) = black_king_file {
+                    rights.black.push(CastlingRight { file: token, king_side: token > king_file });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    rights
+}
+
+// Parse an algebraic square like "e3" into its 0-based file (a=0, h=7)
+// and its rank number (1-8). Returns `None` for anything that isn't
+// exactly a file letter followed by a rank digit.
+fn parse_square(square: &str) -> Option<(usize, usize)> {
+    let mut chars = square.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?;
+    if chars.next().is_some() || !('a'..='h').contains(&file) {
+        return None;
+    }
+    let rank_number = rank.to_digit(10)? as usize;
+    if !(1..=8).contains(&rank_number) {
+        return None;
+    }
+    Some((file as usize - 'a' as usize, rank_number))
+}
+
+// Build the "Castling: ..." summary, e.g. "Castling: White: rook on
+// h-file (king-side), Black: rook on a-file (queen-side)", or
+// "Castling: -" when neither side has any rights left.
+fn describe_castling(rights: &CastlingRights) -> String {
+    let mut parts = Vec::new();
+    for right in &rights.white {
+        parts.push(format!(
+            "White: rook on {}-file ({})",
+            right.file,
+            if right.king_side { "king-side" } else { "queen-side" }
+        ));
+    }
+    for right in &rights.black {
+        parts.push(format!(
+            "Black: rook on {}-file ({})",
+            right.file,
+            if right.king_side { "king-side" } else { "queen-side" }
+        ));
+    }
+
+    if parts.is_empty() {
+        "Castling: -".to_string()
+    } else {
+        format!("Castling: {}", parts.join(", "))
+    }
+}
+
+// Render a piece character as its on-screen glyph, padded with a
+// leading/trailing space: Unicode chess symbols normally, or plain
+// letters when `theme.ascii` is set for terminals without chess-symbol
+// fonts.
+fn piece_glyph(character: char, theme: &Theme) -> &'static str {
+    if theme.ascii {
+        return match character {
+            'r' => " r ",
+            'n' => " n ",
+            'b' => " b ",
+            'q' => " q ",
+            'k' => " k ",
+            'p' => " p ",
+            'R' => " R ",
+            'N' => " N ",
+            'B' => " B ",
+            'Q' => " Q ",
+            'K' => " K ",
+            'P' => " P ",
+            _ => "   ", // Should never happen.
+        };
+    }
+    match character {
+        'r' | 'R' => " ♜ ",
+        'n' | 'N' => " ♞ ",
+        'b' | 'B' => " ♝ ",
+        'q' | 'Q' => " ♛ ",
+        'k' | 'K' => " ♚ ",
+        'p' | 'P' => " ♟︎ ",
+        _ => "   ", // Should never happen.
+    }
+}
+
 // Convert a line of FEN to a line of chessboard.
 // Arguments:
 // - line: The line of FEN to convert.
 // - even: Whether the line is an even or odd rank for the purposes of coloring.
 // - reversed: Whether the line should be reversed for the purposes of orientation
-fn chessify(line: &String, even: bool, reversed: bool) -> String {
+// - en_passant_file: The 0-based file (in board, i.e. unreversed, order) of
+//   the en passant target square on this rank, if any, so that one empty
+//   square can be highlighted instead of colored normally.
+// - theme: The color scheme and glyph table to render with.
+fn chessify(
+    line: &String,
+    even: bool,
+    reversed: bool,
+    en_passant_file: Option<usize>,
+    theme: &Theme,
+) -> String {
     // The output string.
     let mut chessified_line = String::new();
 
@@ -139,6 +729,10 @@ fn chessify(line: &String, even: bool, reversed: bool) -> String {
         false => 1,
     };
 
+    // The 0-based position of the square currently being rendered, in
+    // render order, used to work out its real board file below.
+    let mut square_index = 0;
+
     // Reverse the line if necessary.
     let ordered_line = if reversed {
         line.chars().rev().collect::<String>()
@@ -154,42 +748,40 @@ fn chessify(line: &String, even: bool, reversed: bool) -> String {
             // Safe to unwrap because we know the character is numeric.
             let empty_square_count = character.to_digit(10).unwrap();
             // Add empty squares to the output.
-            // Alternate the color of the squares.
+            // Alternate the color of the squares, except for the en
+            // passant target square, which gets a highlight instead.
             for _ in 0..empty_square_count {
                 square_counter += 1;
-                chessified_line.push_str(match square_counter % 2 {
-                    0 => BACKGROUND_DARK,
-                    _ => BACKGROUND_LIGHT,
+                let file = if reversed { 7 - square_index } else { square_index };
+                chessified_line.push_str(if en_passant_file == Some(file) {
+                    theme.background_en_passant
+                } else {
+                    match square_counter % 2 {
+                        0 => theme.background_dark,
+                        _ => theme.background_light,
+                    }
                 });
                 chessified_line.push_str("   ");
+                square_index += 1;
             }
         } else {
             square_counter += 1;
             // Alternate the color of the squares.
             chessified_line.push_str(match square_counter % 2 {
-                0 => BACKGROUND_DARK,
-                _ => BACKGROUND_LIGHT,
+                0 => theme.background_dark,
+                _ => theme.background_light,
             });
             // Add the piece to the output with the correct color.
-            // Add a space after the piece to make sure the squares are the correct width.
-            chessified_line.push_str(match character {
-                'r' => "\u{1b}[38;5;0m ♜ ",
-                'n' => "\u{1b}[38;5;0m ♞ ",
-                'b' => "\u{1b}[38;5;0m ♝ ",
-                'q' => "\u{1b}[38;5;0m ♛ ",
-                'k' => "\u{1b}[38;5;0m ♚ ",
-                'p' => "\u{1b}[38;5;0m ♟︎ ",
-                'R' => "\u{1b}[38;5;231m ♜ ",
-                'N' => "\u{1b}[38;5;231m ♞ ",
-                'B' => "\u{1b}[38;5;231m ♝ ",
-                'Q' => "\u{1b}[38;5;231m ♛ ",
-                'K' => "\u{1b}[38;5;231m ♚ ",
-                'P' => "\u{1b}[38;5;231m ♟︎ ",
-                _ => " ", // Should never happen.
+            chessified_line.push_str(if character.is_uppercase() {
+                theme.foreground_white
+            } else {
+                theme.foreground_black
             });
+            chessified_line.push_str(piece_glyph(character, theme));
+            square_index += 1;
         }
         // Reset the color to the default so newlines are not colored.
-        chessified_line.push_str(RESET_COLOR);
+        chessified_line.push_str(theme.reset);
     }
 
     chessified_line